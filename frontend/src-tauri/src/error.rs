@@ -0,0 +1,44 @@
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use thiserror::Error;
+
+// Typed error surface for Tauri commands. Serializes as { "kind": ..., "message": ... }
+// so the frontend can branch (and localize) on kind instead of pattern-matching on
+// English error strings.
+#[derive(Debug, Error)]
+pub enum CommandError {
+    #[error("Game not found: {0}")]
+    GameNotFound(String),
+
+    #[error("Invalid path: {0}")]
+    InvalidPath(String),
+
+    #[error("Failed to launch game: {0}")]
+    LaunchFailed(String),
+
+    #[error("Failed to spawn backend sidecar: {0}")]
+    SidecarSpawn(String),
+}
+
+impl CommandError {
+    fn kind(&self) -> &'static str {
+        match self {
+            CommandError::GameNotFound(_) => "GameNotFound",
+            CommandError::InvalidPath(_) => "InvalidPath",
+            CommandError::LaunchFailed(_) => "LaunchFailed",
+            CommandError::SidecarSpawn(_) => "SidecarSpawn",
+        }
+    }
+}
+
+impl Serialize for CommandError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("CommandError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
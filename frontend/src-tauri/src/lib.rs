@@ -1,20 +1,64 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::process::Command;
 use std::sync::Mutex;
 use tauri::{Emitter, Manager};
 
+mod error;
+use error::CommandError;
+
 #[derive(Debug, Serialize, Clone)]
 pub struct DetectedGame {
     pub path: String,
     pub source: String,
+    // Epic's internal app name, needed to build a com.epicgames.launcher:// launch URI.
+    pub app_name: Option<String>,
+    // Epic's catalog item ID, kept alongside app_name for future store-API lookups.
+    pub catalog_item_id: Option<String>,
+    // Whether the storefront reports the game as fully installed. For Steam this is
+    // the fully-installed StateFlags flag, which can also be set while an update is
+    // pending.
+    pub installed: bool,
+    // Human-readable install state, e.g. "FullyInstalled".
+    pub state: String,
+    // Steam's per-app Proton prefix (steamapps/compatdata/<appid>), when found on
+    // Linux. Used to pre-fill a CompatTool so users don't have to locate it by hand.
+    pub compat_data_path: Option<String>,
+}
+
+// Wine/Proton runner configuration for launching the Windows-only exe on Linux.
+#[derive(Debug, Deserialize, Clone)]
+pub struct CompatTool {
+    // Path to the `proton` or `wine` binary.
+    pub runner_path: String,
+    // For Proton: root of the Steam compat prefix (compatdata/<appid>), used as
+    // STEAM_COMPAT_DATA_PATH. The actual Wine prefix passed as WINEPREFIX is the
+    // `pfx` subfolder inside it. For plain Wine: the WINEPREFIX directory itself,
+    // used as-is.
+    pub prefix_path: String,
+    // Steam client install path, required as STEAM_COMPAT_CLIENT_INSTALL_PATH when
+    // runner_path is Proton; unused for plain Wine.
+    pub steam_install_path: Option<String>,
 }
 
 struct BackendProcess {
     child: Option<tauri_plugin_shell::process::CommandChild>,
+    // Restart attempts made within the current rolling window; reset once
+    // RESTART_WINDOW has elapsed since the last restart.
+    restart_count: u32,
+    last_restart: Option<std::time::Instant>,
+    // Set while a graceful shutdown is in flight so the crash handler doesn't
+    // mistake an intentional exit for a crash and try to restart it.
+    shutting_down: bool,
 }
 
+// Maximum restart attempts allowed within a single RESTART_WINDOW before the
+// supervisor gives up and reports backend-startup-failed.
+const MAX_RESTART_ATTEMPTS: u32 = 5;
+const RESTART_WINDOW: std::time::Duration = std::time::Duration::from_secs(60);
+const RESTART_BACKOFF_MS: [u64; 4] = [500, 1000, 2000, 4000];
+
 #[tauri::command]
-fn detect_game_paths() -> Vec<DetectedGame> {
+fn detect_game_paths() -> Result<Vec<DetectedGame>, CommandError> {
     let mut results = Vec::new();
 
     #[cfg(target_os = "windows")]
@@ -24,6 +68,11 @@ fn detect_game_paths() -> Vec<DetectedGame> {
         results.extend(detect_epic());
     }
 
+    #[cfg(target_os = "linux")]
+    {
+        results.extend(detect_steam_linux());
+    }
+
     results.extend(detect_common_paths());
 
     // Deduplicate by normalized path
@@ -33,9 +82,13 @@ fn detect_game_paths() -> Vec<DetectedGame> {
         seen.insert(normalized)
     });
 
-    results
+    Ok(results)
 }
 
+// Steam's appid for Cyberpunk 2077, shared by detection (appmanifest lookup) and
+// store-launch (steam://rungameid/) paths.
+const STEAM_APP_ID: &str = "1091500";
+
 #[cfg(target_os = "windows")]
 fn detect_steam() -> Vec<DetectedGame> {
     use winreg::enums::*;
@@ -80,17 +133,132 @@ fn detect_steam() -> Vec<DetectedGame> {
         }
     }
 
-    // Check each library for Cyberpunk 2077
+    // Check each library's appmanifest for Cyberpunk 2077 (appid 1091500) rather than
+    // guessing the install folder name, which varies by localization/renaming.
     for lib_path in library_paths {
+        let manifest_path = std::path::Path::new(&lib_path)
+            .join("steamapps")
+            .join(format!("appmanifest_{STEAM_APP_ID}.acf"));
+
+        let Ok(manifest) = std::fs::read_to_string(&manifest_path) else {
+            continue;
+        };
+
+        let Some(installdir) = find_vdf_value(&manifest, "installdir") else {
+            continue;
+        };
+
+        let state_flags: u32 = find_vdf_value(&manifest, "StateFlags")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+
+        // The flag with value 4 in StateFlags means "fully installed"; other bits (downloading,
+        // validating, update pending) can be set alongside it, so check the bit
+        // instead of requiring an exact value.
+        if state_flags & 4 == 0 {
+            continue;
+        }
+
         let game_path = std::path::Path::new(&lib_path)
             .join("steamapps")
             .join("common")
-            .join("Cyberpunk 2077");
+            .join(&installdir);
 
         if is_valid_cyberpunk_path(&game_path) {
             results.push(DetectedGame {
                 path: game_path.to_string_lossy().to_string(),
                 source: "Steam".to_string(),
+                app_name: None,
+                catalog_item_id: None,
+                installed: true,
+                state: "FullyInstalled".to_string(),
+                compat_data_path: None,
+            });
+        }
+    }
+
+    results
+}
+
+// Mirrors detect_steam, but for Steam's native Linux install layout (~/.steam/steam
+// or ~/.local/share/Steam) and its Proton compat prefixes, since there's no registry
+// to read on this platform. Proton installs still land under the same
+// steamapps/common/<installdir> layout, so the appmanifest parsing is identical.
+#[cfg(target_os = "linux")]
+fn detect_steam_linux() -> Vec<DetectedGame> {
+    let mut results = Vec::new();
+
+    let Ok(home) = std::env::var("HOME") else {
+        return results;
+    };
+
+    let mut library_paths = vec![
+        format!("{home}/.steam/steam"),
+        format!("{home}/.local/share/Steam"),
+    ];
+
+    for base in library_paths.clone() {
+        let vdf_path = std::path::Path::new(&base)
+            .join("steamapps")
+            .join("libraryfolders.vdf");
+
+        if let Ok(content) = std::fs::read_to_string(&vdf_path) {
+            for line in content.lines() {
+                let trimmed = line.trim();
+                if trimmed.starts_with("\"path\"") {
+                    if let Some(path_value) = extract_vdf_value(trimmed) {
+                        if !library_paths.contains(&path_value) {
+                            library_paths.push(path_value);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    for lib_path in library_paths {
+        let manifest_path = std::path::Path::new(&lib_path)
+            .join("steamapps")
+            .join(format!("appmanifest_{STEAM_APP_ID}.acf"));
+
+        let Ok(manifest) = std::fs::read_to_string(&manifest_path) else {
+            continue;
+        };
+
+        let Some(installdir) = find_vdf_value(&manifest, "installdir") else {
+            continue;
+        };
+
+        let state_flags: u32 = find_vdf_value(&manifest, "StateFlags")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+
+        // See the equivalent check in detect_steam: test the bit, not exact equality.
+        if state_flags & 4 == 0 {
+            continue;
+        }
+
+        let game_path = std::path::Path::new(&lib_path)
+            .join("steamapps")
+            .join("common")
+            .join(&installdir);
+
+        if is_valid_cyberpunk_path(&game_path) {
+            let compat_data_path = std::path::Path::new(&lib_path)
+                .join("steamapps")
+                .join("compatdata")
+                .join(STEAM_APP_ID);
+
+            results.push(DetectedGame {
+                path: game_path.to_string_lossy().to_string(),
+                source: "Steam".to_string(),
+                app_name: None,
+                catalog_item_id: None,
+                installed: true,
+                state: "FullyInstalled".to_string(),
+                compat_data_path: compat_data_path
+                    .exists()
+                    .then(|| compat_data_path.to_string_lossy().to_string()),
             });
         }
     }
@@ -136,6 +304,11 @@ fn detect_gog() -> Vec<DetectedGame> {
                             results.push(DetectedGame {
                                 path: install_loc,
                                 source: "GOG".to_string(),
+                                app_name: None,
+                                catalog_item_id: None,
+                                installed: true,
+                                state: "FullyInstalled".to_string(),
+                                compat_data_path: None,
                             });
                         }
                     }
@@ -191,9 +364,23 @@ fn detect_epic() -> Vec<DetectedGame> {
             if let Some(install_loc) = json.get("InstallLocation").and_then(|v| v.as_str()) {
                 let install_path = std::path::Path::new(install_loc);
                 if is_valid_cyberpunk_path(install_path) {
+                    let app_name = json
+                        .get("AppName")
+                        .and_then(|v| v.as_str())
+                        .map(|s| s.to_string());
+                    let catalog_item_id = json
+                        .get("CatalogItemId")
+                        .and_then(|v| v.as_str())
+                        .map(|s| s.to_string());
+
                     results.push(DetectedGame {
                         path: install_loc.to_string(),
                         source: "Epic".to_string(),
+                        app_name,
+                        catalog_item_id,
+                        installed: true,
+                        state: "FullyInstalled".to_string(),
+                        compat_data_path: None,
                     });
                 }
             }
@@ -223,6 +410,11 @@ fn detect_common_paths() -> Vec<DetectedGame> {
             results.push(DetectedGame {
                 path: path_str.to_string(),
                 source: "Common Path".to_string(),
+                app_name: None,
+                catalog_item_id: None,
+                installed: true,
+                state: "FullyInstalled".to_string(),
+                compat_data_path: None,
             });
         }
     }
@@ -237,7 +429,6 @@ fn is_valid_cyberpunk_path(path: &std::path::Path) -> bool {
         .exists()
 }
 
-#[cfg(target_os = "windows")]
 fn extract_vdf_value(line: &str) -> Option<String> {
     // VDF format: "key"		"value"
     // Find all quote positions and extract the last quoted string
@@ -258,32 +449,133 @@ fn extract_vdf_value(line: &str) -> Option<String> {
     None
 }
 
+// Scans a VDF/ACF document for the first line matching "key" and extracts its value.
+fn find_vdf_value(content: &str, key: &str) -> Option<String> {
+    let quoted_key = format!("\"{key}\"");
+    content
+        .lines()
+        .find(|line| line.trim().starts_with(&quoted_key))
+        .and_then(extract_vdf_value)
+}
+
 #[tauri::command]
 fn launch_game(
+    app: tauri::AppHandle,
     install_path: String,
     exe_relative_path: String,
     launch_args: Option<Vec<String>>,
-) -> Result<(), String> {
-    let exe_path = std::path::Path::new(&install_path).join(&exe_relative_path);
+    source: String,
+    launch_via_store: bool,
+    app_name: Option<String>,
+    compat_tool: Option<CompatTool>,
+) -> Result<(), CommandError> {
+    if launch_via_store {
+        match source.as_str() {
+            "Steam" => return launch_via_url(&app, &format!("steam://rungameid/{STEAM_APP_ID}")),
+            "Epic" => {
+                let app_name = app_name.ok_or_else(|| {
+                    CommandError::InvalidPath("Epic AppName is required for store launch".into())
+                })?;
+                let url = format!(
+                    "com.epicgames.launcher://apps/{app_name}?action=launch&silent=true"
+                );
+                return launch_via_url(&app, &url);
+            }
+            // GOG (and anything else) has no reliable launch URI scheme; fall through
+            // to launching the exe directly.
+            _ => {}
+        }
+    }
+
+    launch_direct(&install_path, &exe_relative_path, launch_args, compat_tool)
+}
+
+// Hands a scheme:// URI to the OS so the owning storefront (Steam/Epic) launches
+// the game, preserving overlays, cloud saves and playtime tracking.
+fn launch_via_url(app: &tauri::AppHandle, url: &str) -> Result<(), CommandError> {
+    use tauri_plugin_opener::OpenerExt;
+
+    app.opener()
+        .open_url(url, None::<&str>)
+        .map_err(|e| CommandError::LaunchFailed(e.to_string()))
+}
+
+fn launch_direct(
+    install_path: &str,
+    exe_relative_path: &str,
+    launch_args: Option<Vec<String>>,
+    compat_tool: Option<CompatTool>,
+) -> Result<(), CommandError> {
+    let exe_path = std::path::Path::new(install_path).join(exe_relative_path);
 
     if !exe_path.exists() {
-        return Err(format!(
-            "Game executable not found: {}",
-            exe_path.display()
-        ));
+        return Err(CommandError::GameNotFound(exe_path.display().to_string()));
     }
 
+    #[cfg(target_os = "linux")]
+    {
+        if let Some(compat_tool) = compat_tool {
+            return launch_via_compat_tool(&exe_path, launch_args, &compat_tool);
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = compat_tool;
+
     let mut cmd = Command::new(&exe_path);
     if let Some(args) = launch_args {
         cmd.args(args);
     }
     cmd.spawn()
-        .map_err(|e| format!("Failed to launch game: {}", e))?;
+        .map_err(|e| CommandError::LaunchFailed(e.to_string()))?;
 
     Ok(())
 }
 
-fn spawn_sidecar(app: &tauri::AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+// Runs the Windows-only exe through a configurable Wine/Proton runner (<runner> run
+// <exe>), with the compat env vars pointed at the prefix so the game finds its saves
+// and registry.
+#[cfg(target_os = "linux")]
+fn launch_via_compat_tool(
+    exe_path: &std::path::Path,
+    launch_args: Option<Vec<String>>,
+    compat_tool: &CompatTool,
+) -> Result<(), CommandError> {
+    let mut cmd = Command::new(&compat_tool.runner_path);
+    cmd.arg("run").arg(exe_path);
+    if let Some(args) = launch_args {
+        cmd.args(args);
+    }
+
+    let is_proton = std::path::Path::new(&compat_tool.runner_path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.to_lowercase().contains("proton"));
+
+    if is_proton {
+        let steam_install_path = compat_tool.steam_install_path.as_ref().ok_or_else(|| {
+            CommandError::InvalidPath(
+                "steam_install_path is required to launch through Proton".to_string(),
+            )
+        })?;
+        cmd.env("STEAM_COMPAT_DATA_PATH", &compat_tool.prefix_path);
+        cmd.env(
+            "WINEPREFIX",
+            std::path::Path::new(&compat_tool.prefix_path).join("pfx"),
+        );
+        cmd.env("STEAM_COMPAT_CLIENT_INSTALL_PATH", steam_install_path);
+    } else {
+        // Plain Wine: prefix_path is the WINEPREFIX itself, not a Proton compatdata
+        // root with a nested pfx/ subfolder, so use it directly.
+        cmd.env("WINEPREFIX", &compat_tool.prefix_path);
+    }
+
+    cmd.spawn()
+        .map_err(|e| CommandError::LaunchFailed(e.to_string()))?;
+
+    Ok(())
+}
+
+fn spawn_sidecar(app: &tauri::AppHandle) -> Result<(), CommandError> {
     use tauri_plugin_shell::ShellExt;
 
     log::info!("Spawning backend sidecar...");
@@ -298,18 +590,19 @@ fn spawn_sidecar(app: &tauri::AppHandle) -> Result<(), Box<dyn std::error::Error
     let sidecar_command = app
         .shell()
         .sidecar("binaries/rmm-backend")
-        .map_err(|e| format!("Failed to create sidecar command: {e}"))?
+        .map_err(|e| CommandError::SidecarSpawn(format!("Failed to create sidecar command: {e}")))?
         .env("RMM_DATA_DIR", data_dir.to_string_lossy().to_string());
 
     let (mut rx, child) = sidecar_command
         .spawn()
-        .map_err(|e| format!("Failed to spawn sidecar: {e}"))?;
+        .map_err(|e| CommandError::SidecarSpawn(format!("Failed to spawn sidecar: {e}")))?;
 
     // Store the child process handle for cleanup
     let state = app.state::<Mutex<BackendProcess>>();
     match state.lock() {
         Ok(mut bp) => {
             bp.child = Some(child);
+            bp.shutting_down = false;
         }
         Err(e) => {
             log::error!("Failed to lock BackendProcess for init: {}", e);
@@ -331,7 +624,20 @@ fn spawn_sidecar(app: &tauri::AppHandle) -> Result<(), Box<dyn std::error::Error
                 }
                 CommandEvent::Terminated(payload) => {
                     log::warn!("Backend process terminated: {:?}", payload);
+
+                    let shutting_down = app_handle
+                        .state::<Mutex<BackendProcess>>()
+                        .lock()
+                        .map(|bp| bp.shutting_down)
+                        .unwrap_or(false);
+
+                    if shutting_down {
+                        // Expected exit from shutdown_sidecar_blocking's graceful shutdown; not a crash.
+                        break;
+                    }
+
                     let _ = app_handle.emit("backend-crashed", ());
+                    supervise_restart(&app_handle);
                     break;
                 }
                 CommandEvent::Error(err) => {
@@ -389,19 +695,144 @@ fn spawn_sidecar(app: &tauri::AppHandle) -> Result<(), Box<dyn std::error::Error
     Ok(())
 }
 
-fn kill_sidecar(app: &tauri::AppHandle) {
+#[derive(Serialize, Clone)]
+struct RestartingPayload {
+    attempt: u32,
+}
+
+// Re-spawns the backend sidecar after an unexpected crash, backing off between
+// attempts and giving up once MAX_RESTART_ATTEMPTS is exhausted within the rolling
+// RESTART_WINDOW.
+fn supervise_restart(app: &tauri::AppHandle) {
     let state = app.state::<Mutex<BackendProcess>>();
-    match state.lock() {
-        Ok(mut bp) => {
-            if let Some(child) = bp.child.take() {
-                log::info!("Killing backend sidecar...");
-                let _ = child.kill();
+    let attempt = {
+        let mut bp = match state.lock() {
+            Ok(bp) => bp,
+            Err(e) => {
+                log::error!("Failed to lock BackendProcess for restart: {}", e);
+                return;
             }
+        };
+
+        let now = std::time::Instant::now();
+        if bp
+            .last_restart
+            .map(|t| now.duration_since(t) > RESTART_WINDOW)
+            .unwrap_or(true)
+        {
+            bp.restart_count = 0;
         }
-        Err(e) => {
-            log::error!("Failed to lock BackendProcess for cleanup: {}", e);
+
+        if bp.restart_count >= MAX_RESTART_ATTEMPTS {
+            log::error!("Backend exceeded {MAX_RESTART_ATTEMPTS} restart attempts, giving up");
+            drop(bp);
+            let _ = app.emit("backend-startup-failed", ());
+            return;
+        }
+
+        bp.restart_count += 1;
+        bp.last_restart = Some(now);
+        bp.restart_count
+    };
+
+    let delay_ms = RESTART_BACKOFF_MS[(attempt as usize - 1).min(RESTART_BACKOFF_MS.len() - 1)];
+    log::warn!("Restarting backend sidecar (attempt {attempt}/{MAX_RESTART_ATTEMPTS}) in {delay_ms}ms");
+    let _ = app.emit("backend-restarting", RestartingPayload { attempt });
+
+    let app_handle = app.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(delay_ms));
+
+        // The user may have quit while we were waiting out the backoff; don't
+        // resurrect the backend after shutdown_sidecar_blocking has already started tearing it down.
+        let shutting_down = app_handle
+            .state::<Mutex<BackendProcess>>()
+            .lock()
+            .map(|bp| bp.shutting_down)
+            .unwrap_or(false);
+        if shutting_down {
+            log::info!("Skipping scheduled backend restart; shutdown is in progress");
+            return;
+        }
+
+        if let Err(e) = spawn_sidecar(&app_handle) {
+            log::error!("Failed to restart backend sidecar: {}", e);
+            // spawn_sidecar failed outright, so no CommandEvent::Terminated will ever
+            // fire to trigger a further retry — drive the same backoff budget here.
+            supervise_restart(&app_handle);
+        }
+    });
+}
+
+// Address the same single-TCP-connection health/control endpoint the sidecar exposes.
+const BACKEND_ADDR: &str = "127.0.0.1:8425";
+
+// Best-effort request for the backend to shut itself down cleanly, so in-flight DB
+// writes and lockfiles aren't left in a bad state. Uses the same single-TCP-connection
+// pattern as the startup health poll; the response (if any) is ignored.
+fn request_graceful_shutdown() {
+    use std::io::Write;
+
+    if let Ok(mut stream) = std::net::TcpStream::connect(BACKEND_ADDR) {
+        let _ = stream.set_write_timeout(Some(std::time::Duration::from_secs(1)));
+        let request = format!(
+            "POST /shutdown HTTP/1.1\r\nHost: {BACKEND_ADDR}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+        );
+        let _ = stream.write_all(request.as_bytes());
+    }
+}
+
+// Blocking shutdown handshake: request a graceful stop, poll the control port until
+// it stops accepting connections, and force-kill only if a 5s deadline passes first.
+// Must be run off the event-loop thread by callers.
+fn shutdown_sidecar_blocking(app: &tauri::AppHandle) {
+    let child = {
+        let state = app.state::<Mutex<BackendProcess>>();
+        match state.lock() {
+            Ok(mut bp) => {
+                bp.shutting_down = true;
+                bp.child.take()
+            }
+            Err(e) => {
+                log::error!("Failed to lock BackendProcess for cleanup: {}", e);
+                None
+            }
         }
-    }; // Semicolon drops MutexGuard before `state`
+    };
+
+    let Some(child) = child else {
+        return;
+    };
+
+    log::info!("Requesting graceful backend shutdown...");
+    request_graceful_shutdown();
+
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    let poll_interval = std::time::Duration::from_millis(200);
+
+    while std::time::Instant::now() < deadline {
+        // Once the backend is down, its control port stops accepting connections.
+        if std::net::TcpStream::connect_timeout(
+            &BACKEND_ADDR.parse().expect("valid socket address"),
+            poll_interval,
+        )
+        .is_err()
+        {
+            log::info!("Backend shut down gracefully");
+            return;
+        }
+        std::thread::sleep(poll_interval);
+    }
+
+    log::warn!("Backend did not shut down within timeout, force-killing");
+    let _ = child.kill();
+}
+
+// Fire-and-forget cleanup for window teardown, where nothing needs to wait on the
+// shutdown handshake finishing.
+fn kill_sidecar(app: &tauri::AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || shutdown_sidecar_blocking(&app));
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -423,7 +854,12 @@ pub fn run() {
     }
 
     builder
-        .manage(Mutex::new(BackendProcess { child: None }))
+        .manage(Mutex::new(BackendProcess {
+            child: None,
+            restart_count: 0,
+            last_restart: None,
+            shutting_down: false,
+        }))
         .invoke_handler(tauri::generate_handler![detect_game_paths, launch_game])
         .setup(|app| {
             app.handle().plugin(
@@ -454,6 +890,20 @@ pub fn run() {
                 }
             }
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app_handle, event| {
+            // Also clean up on app quit (e.g. Cmd+Q / OS shutdown), not only when the
+            // main window is destroyed. Unlike the window-Destroyed path, this one has
+            // to actually delay process exit until the shutdown handshake is done,
+            // otherwise Tauri's default exit races (and usually wins) against it.
+            if let tauri::RunEvent::ExitRequested { api, .. } = event {
+                api.prevent_exit();
+                let app_handle = app_handle.clone();
+                std::thread::spawn(move || {
+                    shutdown_sidecar_blocking(&app_handle);
+                    app_handle.exit(0);
+                });
+            }
+        });
 }